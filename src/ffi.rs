@@ -22,10 +22,19 @@
 
 #![allow(non_snake_case, non_camel_case_types, dead_code, non_upper_case_globals)]
 
-use libc::{ c_int, c_char, LPOVERLAPPED, HANDLE, DWORD, WORD, BOOL, BYTE, SECURITY_ATTRIBUTES };
+use libc::consts::os::extra::*;
+use libc::{ c_int, c_char, HANDLE, DWORD, WORD, BOOL, BYTE, SECURITY_ATTRIBUTES };
 
 pub const ERROR_INVALID_USER_BUFFER: c_int = 1784;
 pub const ERROR_NOT_ENOUGH_MEMORY: c_int = 8;
+pub const ERROR_IO_PENDING: c_int = 997;
+pub const ERROR_INSUFFICIENT_BUFFER: c_int = 122;
+
+pub const FILE_FLAG_OVERLAPPED: DWORD = 0x40000000;
+
+pub const WAIT_OBJECT_0: DWORD = 0x00000000;
+pub const WAIT_TIMEOUT: DWORD = 0x00000102;
+pub const INFINITE: DWORD = 0xFFFFFFFF;
 
 bitflags!{
 	#[repr(C)]
@@ -81,9 +90,44 @@ impl DCB {
 			DTR_CONTROL::HANDSHAKE => self.flags.insert(DCBFDtrControl_lo | DCBFDtrControl_hi),
 		}
 	}
+
+	pub fn set_rts_control(&mut self, control: RTS_CONTROL) {
+		match control {
+			RTS_CONTROL::DISABLE => self.flags.remove(DCBFRtsControl_lo | DCBFRtsControl_hi),
+			RTS_CONTROL::ENABLE => {
+				self.flags.remove(DCBFRtsControl_hi);
+				self.flags.insert(DCBFRtsControl_lo)
+			},
+			RTS_CONTROL::HANDSHAKE => self.flags.insert(DCBFRtsControl_lo | DCBFRtsControl_hi),
+		}
+	}
+
+	pub fn cts_flow(&self) -> bool {
+		self.flags.contains(DCBFOutxCtsFlow)
+	}
+
+	pub fn set_cts_flow(&mut self, enable: bool) {
+		if enable {
+			self.flags.insert(DCBFOutxCtsFlow)
+		} else {
+			self.flags.remove(DCBFOutxCtsFlow)
+		}
+	}
+
+	pub fn xon_xoff_flow(&self) -> bool {
+		self.flags.contains(DCBFOutX | DCBFInX)
+	}
+
+	pub fn set_xon_xoff_flow(&mut self, enable: bool) {
+		if enable {
+			self.flags.insert(DCBFOutX | DCBFInX)
+		} else {
+			self.flags.remove(DCBFOutX | DCBFInX)
+		}
+	}
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum Parity {
 	NO = 0,
 	ODD = 1,
@@ -91,13 +135,53 @@ pub enum Parity {
 	MARK = 3,
 	SPACE = 4,
 }
+impl Parity {
+	pub fn to_byte(&self) -> BYTE {
+		match *self {
+			Parity::NO => NOPARITY,
+			Parity::ODD => ODDPARITY,
+			Parity::EVEN => EVENPARITY,
+			Parity::MARK => MARKPARITY,
+			Parity::SPACE => SPACEPARITY,
+		}
+	}
 
-#[derive(Debug, Clone)]
+	pub fn from_byte(byte: BYTE) -> Option<Parity> {
+		match byte {
+			NOPARITY => Some(Parity::NO),
+			ODDPARITY => Some(Parity::ODD),
+			EVENPARITY => Some(Parity::EVEN),
+			MARKPARITY => Some(Parity::MARK),
+			SPACEPARITY => Some(Parity::SPACE),
+			_ => None,
+		}
+	}
+}
+
+#[derive(Debug, Clone, PartialEq)]
 pub enum StopBits {
 	ONE = 0,
 	ONE5 = 1,
 	TWO = 2,
 }
+impl StopBits {
+	pub fn to_byte(&self) -> BYTE {
+		match *self {
+			StopBits::ONE => ONESTOPBIT,
+			StopBits::ONE5 => ONE5STOPBITS,
+			StopBits::TWO => TWOSTOPBITS,
+		}
+	}
+
+	pub fn from_byte(byte: BYTE) -> Option<StopBits> {
+		match byte {
+			ONESTOPBIT => Some(StopBits::ONE),
+			ONE5STOPBITS => Some(StopBits::ONE5),
+			TWOSTOPBITS => Some(StopBits::TWO),
+			_ => None,
+		}
+	}
+}
 
 #[derive(Debug, Clone)]
 pub enum DTR_CONTROL {
@@ -106,6 +190,13 @@ pub enum DTR_CONTROL {
 	HANDSHAKE,
 }
 
+#[derive(Debug, Clone)]
+pub enum RTS_CONTROL {
+	DISABLE,
+	ENABLE,
+	HANDSHAKE,
+}
+
 bitflags!{
 	#[repr(C)]
 	flags CommEventFlags: DWORD {
@@ -121,6 +212,23 @@ bitflags!{
 	}
 }
 
+pub const SETRTS: DWORD = 3;
+pub const CLRRTS: DWORD = 4;
+pub const SETDTR: DWORD = 5;
+pub const CLRDTR: DWORD = 6;
+pub const SETBREAK: DWORD = 8;
+pub const CLRBREAK: DWORD = 9;
+
+bitflags!{
+	#[repr(C)]
+	flags ModemStatusFlags: DWORD {
+		const MS_CTS_ON = 0x0010,
+		const MS_DSR_ON = 0x0020,
+		const MS_RING_ON = 0x0040,
+		const MS_RLSD_ON = 0x0080,
+	}
+}
+
 bitflags!{
 	#[repr(C)]
 	flags PurgeFlags: DWORD {
@@ -140,6 +248,17 @@ pub struct COMMTIMEOUTS {
 	pub WriteTotalTimeoutConstant: DWORD,
 }
 
+/// Mirrors the Win32 `OVERLAPPED` struct used to track the state of an asynchronous
+/// (overlapped) I/O operation
+#[repr(C)]
+pub struct OVERLAPPED {
+	pub Internal: usize,
+	pub InternalHigh: usize,
+	pub Offset: DWORD,
+	pub OffsetHigh: DWORD,
+	pub hEvent: HANDLE,
+}
+
 #[link(name = "kernel32")]
 extern "system" {
 	pub fn PurgeComm(file_handle: HANDLE, flags: PurgeFlags) -> BOOL;
@@ -147,9 +266,34 @@ extern "system" {
 	pub fn SetCommState(file_handle: HANDLE, dcb: *mut DCB) -> BOOL;
 	pub fn SetCommMask(file_handle: HANDLE, event_mask: CommEventFlags) -> BOOL;
 	pub fn WaitCommEvent(file_handle: HANDLE, event_mask: *mut CommEventFlags,
-		overlapped: LPOVERLAPPED) -> BOOL;
+		overlapped: *mut OVERLAPPED) -> BOOL;
 	pub fn SetCommTimeouts(file_handle: HANDLE, comm_timeouts: *mut COMMTIMEOUTS) -> BOOL;
 	pub fn CreateFileA(lpFileName: *const c_char, dwDesiredAccess: DWORD, dwShareMode: DWORD,
 		lpSecurityAttributes: *mut SECURITY_ATTRIBUTES, dwCreationDisposition: DWORD,
 		dwFlagsAndAttributes: DWORD, hTemplateFile: HANDLE) -> HANDLE;
+	pub fn CreateEventW(lpEventAttributes: *mut SECURITY_ATTRIBUTES, bManualReset: BOOL,
+		bInitialState: BOOL, lpName: *const u16) -> HANDLE;
+	pub fn WaitForSingleObject(hHandle: HANDLE, dwMilliseconds: DWORD) -> DWORD;
+	pub fn GetOverlappedResult(hFile: HANDLE, lpOverlapped: *mut OVERLAPPED,
+		lpNumberOfBytesTransferred: *mut DWORD, bWait: BOOL) -> BOOL;
+	pub fn CancelIo(hFile: HANDLE) -> BOOL;
+	pub fn ResetEvent(hEvent: HANDLE) -> BOOL;
+	pub fn EscapeCommFunction(hFile: HANDLE, dwFunc: DWORD) -> BOOL;
+	pub fn GetCommModemStatus(hFile: HANDLE, lpModemStat: *mut ModemStatusFlags) -> BOOL;
+	pub fn QueryDosDeviceW(lpDeviceName: *const u16, lpTargetPath: *mut u16, ucchMax: DWORD)
+		-> DWORD;
+}
+
+#[test]
+fn parity_to_byte_and_back_round_trips() {
+	for parity in &[Parity::NO, Parity::ODD, Parity::EVEN, Parity::MARK, Parity::SPACE] {
+		assert_eq!(Parity::from_byte(parity.to_byte()), Some(parity.clone()));
+	}
+}
+
+#[test]
+fn stop_bits_to_byte_and_back_round_trips() {
+	for stop_bits in &[StopBits::ONE, StopBits::ONE5, StopBits::TWO] {
+		assert_eq!(StopBits::from_byte(stop_bits.to_byte()), Some(stop_bits.clone()));
+	}
 }
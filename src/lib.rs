@@ -30,7 +30,7 @@ pub use ffi::*;
 
 use libc::consts::os::extra::*;
 use libc::funcs::extra::kernel32;
-use libc::{ c_void, c_int, HANDLE };
+use libc::{ c_void, c_int, HANDLE, DWORD };
 use std::{ ptr, mem, io };
 use std::io::{ Error, ErrorKind };
 use std::cell::RefCell;
@@ -55,54 +55,385 @@ fn system_to_io_err(operation: &'static str, error_code: c_int) -> io::Error {
 			operation, error_code, message))
 }
 
+/// The handshaking scheme used to keep a sender from overrunning a receiver
+#[derive(Debug, Clone, PartialEq)]
+pub enum FlowControl {
+	/// No handshaking; the sender transmits whenever it likes
+	None,
+	/// Hardware handshaking via the RTS/CTS lines
+	RtsCts,
+	/// Software handshaking via in-band XON/XOFF characters
+	XonXoff,
+}
+
+fn flow_control_of_dcb(dcb: &DCB) -> FlowControl {
+	if dcb.cts_flow() {
+		FlowControl::RtsCts
+	} else if dcb.xon_xoff_flow() {
+		FlowControl::XonXoff
+	} else {
+		FlowControl::None
+	}
+}
+
+fn apply_flow_control(dcb: &mut DCB, flow_control: &FlowControl) {
+	match *flow_control {
+		FlowControl::None => {
+			dcb.set_cts_flow(false);
+			dcb.set_xon_xoff_flow(false);
+			dcb.set_rts_control(RTS_CONTROL::DISABLE);
+		},
+		FlowControl::RtsCts => {
+			dcb.set_cts_flow(true);
+			dcb.set_xon_xoff_flow(false);
+			dcb.set_rts_control(RTS_CONTROL::HANDSHAKE);
+		},
+		FlowControl::XonXoff => {
+			dcb.set_cts_flow(false);
+			dcb.set_rts_control(RTS_CONTROL::DISABLE);
+			dcb.set_xon_xoff_flow(true);
+			dcb.XonChar = 0x11;
+			dcb.XoffChar = 0x13;
+		},
+	}
+}
+
+#[test]
+fn flow_control_round_trips_through_dcb() {
+	for flow_control in &[FlowControl::None, FlowControl::RtsCts, FlowControl::XonXoff] {
+		let mut dcb = unsafe { mem::zeroed() };
+		apply_flow_control(&mut dcb, flow_control);
+		assert_eq!(flow_control_of_dcb(&dcb), *flow_control);
+	}
+}
+
+/// Write every field of `settings` into `dcb`, so callers can fold it into a larger DCB
+/// mutation (e.g. `Connection::new` also setting DTR) without paying for an extra
+/// `GetCommState`/`SetCommState` round trip.
+fn write_settings_to_dcb(dcb: &mut DCB, settings: &Settings) {
+	dcb.BaudRate = settings.baud_rate;
+	dcb.ByteSize = settings.byte_size;
+	dcb.Parity = settings.parity.to_byte();
+	dcb.StopBits = settings.stop_bits.to_byte();
+	apply_flow_control(dcb, &settings.flow_control);
+}
+
+/// The full set of port attributes covered by `Connection::apply_settings`/`Connection::settings`
+#[derive(Debug, Clone)]
+pub struct Settings {
+	pub baud_rate: u32,
+	pub byte_size: u8,
+	pub parity: Parity,
+	pub stop_bits: StopBits,
+	pub flow_control: FlowControl,
+}
+
+/// Open `port`, optionally with `FILE_FLAG_OVERLAPPED` for asynchronous I/O
+fn open_handle(port: &str, overlapped: bool) -> io::Result<HANDLE> {
+	let flags = if overlapped {
+		libc::FILE_ATTRIBUTE_NORMAL | FILE_FLAG_OVERLAPPED
+	} else {
+		libc::FILE_ATTRIBUTE_NORMAL
+	};
+
+	let (comm_handle, err) = unsafe {
+		let mut port_u16: Vec<_> = port.utf16_units().collect();
+		port_u16.push(0);
+		(
+			kernel32::CreateFileW(port_u16.as_ptr(),
+				GENERIC_READ | GENERIC_WRITE,
+				0,
+				ptr::null_mut(),
+				OPEN_EXISTING,
+				flags,
+				ptr::null_mut()),
+			kernel32::GetLastError() as c_int
+		)
+	};
+
+	if comm_handle == INVALID_HANDLE_VALUE {
+		Err(system_to_io_err("Open port", err))
+	} else {
+		Ok(comm_handle)
+	}
+}
+
+/// The two directions an overlapped transfer can be in flight for, each needing its own
+/// manual-reset event to wait on
+struct OverlappedIo {
+	read_event: HANDLE,
+	write_event: HANDLE,
+}
+impl Drop for OverlappedIo {
+	fn drop(&mut self) {
+		unsafe {
+			kernel32::CloseHandle(self.read_event);
+			kernel32::CloseHandle(self.write_event);
+		}
+	}
+}
+
 /// A serial connection
 pub struct Connection {
 	// Pointer to the serial connection
-	comm_handle: RefCell<HANDLE>
+	comm_handle: RefCell<HANDLE>,
+	// The port this connection was opened with, kept around so the handle can be reopened
+	// when overlapped mode is toggled
+	port: String,
+	// Current read/write timeout, used to bound `WaitForSingleObject` in overlapped mode
+	timeout_ms: RefCell<u32>,
+	// `Some` when the handle was opened with `FILE_FLAG_OVERLAPPED`
+	overlapped: RefCell<Option<OverlappedIo>>,
 }
 impl Connection {
 	/// Open a new connection via port `port` with baud rate `baud_rate`
 	pub fn new(port: &str, baud_rate: u32) -> io::Result<Connection> {
-		let (comm_handle, err) = unsafe {
-			let mut port_u16: Vec<_> = port.utf16_units().collect();
-			port_u16.push(0);
-			(
-				kernel32::CreateFileW(port_u16.as_ptr(),
-					GENERIC_READ | GENERIC_WRITE,
-					0,
-					ptr::null_mut(),
-					OPEN_EXISTING,
-					libc::FILE_ATTRIBUTE_NORMAL,
-					ptr::null_mut()),
-				kernel32::GetLastError() as c_int
-			)
+		let comm_handle = try!(open_handle(port, false));
+
+		let mut conn = Connection{
+			comm_handle: RefCell::new(comm_handle),
+			port: port.to_string(),
+			timeout_ms: RefCell::new(40),
+			overlapped: RefCell::new(None),
 		};
 
-		if comm_handle == INVALID_HANDLE_VALUE {
-			Err(system_to_io_err("Open port", err))
+		conn.comm_state()
+			.map(|mut dcb| {
+				dcb.set_dtr_control(DTR_CONTROL::ENABLE);
+				write_settings_to_dcb(&mut dcb, &Settings{
+					baud_rate: baud_rate,
+					byte_size: 8,
+					parity: Parity::NO,
+					stop_bits: StopBits::ONE,
+					flow_control: FlowControl::None,
+				});
+				dcb
+			})
+			.and_then(|dcb| conn.set_comm_state(dcb))
+			.and_then(|_| {
+				unsafe {
+					PurgeComm(*conn.comm_handle.borrow_mut(), PURGE_RXCLEAR | PURGE_TXCLEAR);
+				}
+				conn.set_timeout(40)
+			})
+			.map(|_| conn)
+	}
+
+	/// Switch this connection's handle between blocking and overlapped (asynchronous) I/O.
+	///
+	/// When `overlapped` is `true`, the handle is reopened with `FILE_FLAG_OVERLAPPED` and a
+	/// reusable manual-reset event is created for each of the read and write directions;
+	/// `read`/`write` then drive every transfer through those events instead of blocking the
+	/// calling thread for the whole transfer, and `cancel_pending` becomes meaningful.
+	pub fn set_overlapped(&mut self, overlapped: bool) -> io::Result<()> {
+		// COM ports are opened exclusively (dwShareMode = 0), so CreateFileW would fail with
+		// the old handle still open. The old handle is closed first; if the reopen below
+		// fails, `comm_handle` is left at `INVALID_HANDLE_VALUE` rather than a stale handle,
+		// so any further operation on this connection fails explicitly instead of silently
+		// touching a closed handle.
+		unsafe { kernel32::CloseHandle(*self.comm_handle.borrow_mut()); }
+		*self.comm_handle.borrow_mut() = INVALID_HANDLE_VALUE;
+		*self.overlapped.borrow_mut() = None;
+
+		let new_handle = try!(open_handle(&self.port, overlapped));
+
+		let new_overlapped = if overlapped {
+			let read_event = unsafe { CreateEventW(ptr::null_mut(), 1, 0, ptr::null()) };
+			if read_event.is_null() {
+				let err = unsafe { kernel32::GetLastError() as c_int };
+				unsafe { kernel32::CloseHandle(new_handle); }
+				return Err(system_to_io_err("CreateEventW", err));
+			}
+
+			let write_event = unsafe { CreateEventW(ptr::null_mut(), 1, 0, ptr::null()) };
+			if write_event.is_null() {
+				let err = unsafe { kernel32::GetLastError() as c_int };
+				unsafe {
+					kernel32::CloseHandle(read_event);
+					kernel32::CloseHandle(new_handle);
+				}
+				return Err(system_to_io_err("CreateEventW", err));
+			}
+
+			Some(OverlappedIo{ read_event: read_event, write_event: write_event })
+		} else {
+			None
+		};
+
+		*self.comm_handle.borrow_mut() = new_handle;
+		*self.overlapped.borrow_mut() = new_overlapped;
+		Ok(())
+	}
+
+	/// Cancel any I/O operations currently in flight on this connection's handle
+	pub fn cancel_pending(&mut self) -> io::Result<()> {
+		let (succeded, err) = unsafe { (
+			CancelIo(*self.comm_handle.borrow_mut()) != 0,
+			kernel32::GetLastError() as c_int
+		)};
+
+		if succeded {
+			Ok(())
+		} else {
+			Err(system_to_io_err("CancelIo", err))
+		}
+	}
+
+	fn escape_comm_function(&mut self, function: DWORD) -> io::Result<()> {
+		let (succeded, err) = unsafe { (
+			EscapeCommFunction(*self.comm_handle.borrow_mut(), function) != 0,
+			kernel32::GetLastError() as c_int
+		)};
+
+		if succeded {
+			Ok(())
+		} else {
+			Err(system_to_io_err("EscapeCommFunction", err))
+		}
+	}
+
+	/// Assert or deassert the RTS (Request To Send) line
+	pub fn set_rts(&mut self, on: bool) -> io::Result<()> {
+		self.escape_comm_function(if on { SETRTS } else { CLRRTS })
+	}
+
+	/// Assert or deassert the DTR (Data Terminal Ready) line
+	pub fn set_dtr(&mut self, on: bool) -> io::Result<()> {
+		self.escape_comm_function(if on { SETDTR } else { CLRDTR })
+	}
+
+	/// Assert or deassert a break condition on the line
+	pub fn set_break(&mut self, on: bool) -> io::Result<()> {
+		self.escape_comm_function(if on { SETBREAK } else { CLRBREAK })
+	}
+
+	/// The current state of the modem control input lines (CTS, DSR, ring, carrier detect)
+	pub fn modem_status(&mut self) -> io::Result<ModemStatusFlags> {
+		let mut status = unsafe { mem::zeroed() };
+		let (succeded, err) = unsafe { (
+			GetCommModemStatus(*self.comm_handle.borrow_mut(), &mut status) != 0,
+			kernel32::GetLastError() as c_int
+		)};
+
+		if succeded {
+			Ok(status)
+		} else {
+			Err(system_to_io_err("GetCommModemStatus", err))
+		}
+	}
+
+	/// Whether the CTS (Clear To Send) line is currently asserted
+	pub fn cts(&mut self) -> io::Result<bool> {
+		self.modem_status().map(|s| s.contains(MS_CTS_ON))
+	}
+
+	/// Whether the DSR (Data Set Ready) line is currently asserted
+	pub fn dsr(&mut self) -> io::Result<bool> {
+		self.modem_status().map(|s| s.contains(MS_DSR_ON))
+	}
+
+	/// Whether the ring indicator line is currently asserted
+	pub fn ring(&mut self) -> io::Result<bool> {
+		self.modem_status().map(|s| s.contains(MS_RING_ON))
+	}
+
+	/// Whether the carrier detect (RLSD) line is currently asserted
+	pub fn carrier_detect(&mut self) -> io::Result<bool> {
+		self.modem_status().map(|s| s.contains(MS_RLSD_ON))
+	}
+
+	/// Drive a `ReadFile`/`WriteFile`/`WaitCommEvent` call through to completion, blocking on
+	/// `event` via `WaitForSingleObject` if the operation is left pending. Used by the
+	/// overlapped read and write paths and by `wait_event`.
+	fn finish_overlapped(&mut self,
+		succeded: bool,
+		err: c_int,
+		event: HANDLE,
+		overlapped: &mut OVERLAPPED,
+		timeout_ms: u32,
+		n_bytes_immediate: DWORD) -> io::Result<usize>
+	{
+		if succeded {
+			return Ok(n_bytes_immediate as usize);
+		}
+
+		if err != ERROR_IO_PENDING {
+			return Err(system_to_io_err("overlapped I/O", err));
+		}
+
+		let wait_result = unsafe { WaitForSingleObject(event, timeout_ms) };
+		if wait_result == WAIT_TIMEOUT {
+			try!(self.cancel_pending());
+			// `CancelIo` only requests cancellation; it does not wait for the driver to
+			// actually stop touching `overlapped` and the caller's buffer. Block here until
+			// the cancellation completes, otherwise this function could return and drop
+			// `overlapped` off the stack while the driver still has a pending write to it.
+			let mut n_bytes = 0;
+			unsafe {
+				GetOverlappedResult(*self.comm_handle.borrow_mut(), overlapped, &mut n_bytes, 1);
+			}
+			return Err(Error::new(ErrorKind::TimedOut, "Operation timed out"));
+		} else if wait_result != WAIT_OBJECT_0 {
+			let err = unsafe { kernel32::GetLastError() as c_int };
+			return Err(system_to_io_err("WaitForSingleObject", err));
+		}
+
+		let mut n_bytes = 0;
+		let (succeded, err) = unsafe { (
+			GetOverlappedResult(*self.comm_handle.borrow_mut(), overlapped, &mut n_bytes, 0) != 0,
+			kernel32::GetLastError() as c_int
+		)};
+
+		if succeded {
+			Ok(n_bytes as usize)
 		} else {
-			let mut conn = Connection{ comm_handle: RefCell::new(comm_handle) };
-
-			conn.comm_state()
-				.map(|mut dcb| {
-					dcb.set_dtr_control(DTR_CONTROL::ENABLE);
-					dcb
-				})
-				.and_then(|dcb| conn.set_comm_state(dcb))
-				.and_then(|_| conn.set_baud_rate(baud_rate))
-				.and_then(|_| conn.set_byte_size(8))
-				.and_then(|_| conn.set_stop_bits(ONESTOPBIT))
-				.and_then(|_| conn.set_parity(NOPARITY))
-				.and_then(|_| {
-					unsafe {
-						PurgeComm(*conn.comm_handle.borrow_mut(), PURGE_RXCLEAR | PURGE_TXCLEAR);
-					}
-					conn.set_timeout(40)
-				})
-				.map(|_| conn)					
+			Err(system_to_io_err("GetOverlappedResult", err))
 		}
 	}
 
+	/// Block until one of the events in `mask` occurs on this connection, returning the events
+	/// that actually fired. Waits forever if `timeout_ms` is `None`, otherwise returns
+	/// `ErrorKind::TimedOut` once `timeout_ms` elapses without an event.
+	pub fn wait_event(&mut self, mask: CommEventFlags, timeout_ms: Option<u32>)
+		-> io::Result<CommEventFlags>
+	{
+		if self.overlapped.borrow().is_none() {
+			return Err(Error::new(ErrorKind::InvalidInput,
+				"wait_event requires the connection to be in overlapped mode; \
+				call set_overlapped(true) first"));
+		}
+
+		let (succeded, err) = unsafe { (
+			SetCommMask(*self.comm_handle.borrow_mut(), mask) != 0,
+			kernel32::GetLastError() as c_int
+		)};
+		if !succeded {
+			return Err(system_to_io_err("SetCommMask", err));
+		}
+
+		let event = unsafe { CreateEventW(ptr::null_mut(), 1, 0, ptr::null()) };
+		if event.is_null() {
+			let err = unsafe { kernel32::GetLastError() as c_int };
+			return Err(system_to_io_err("CreateEventW", err));
+		}
+
+		let mut overlapped = unsafe { mem::zeroed::<OVERLAPPED>() };
+		overlapped.hEvent = event;
+
+		let mut fired = CommEventFlags::empty();
+		let (succeded, err) = unsafe { (
+			WaitCommEvent(*self.comm_handle.borrow_mut(), &mut fired, &mut overlapped) != 0,
+			kernel32::GetLastError() as c_int
+		)};
+
+		let result = self.finish_overlapped(succeded, err, event, &mut overlapped,
+			timeout_ms.unwrap_or(INFINITE), 0);
+
+		unsafe { kernel32::CloseHandle(event); }
+
+		result.map(|_| fired)
+	}
+
 	/// Retrieve the current control settings for this communications device
 	fn comm_state(&self) -> io::Result<DCB> {
 		let mut dcb = unsafe { mem::zeroed() };
@@ -147,6 +478,7 @@ impl Connection {
 		)};
 
 		if succeded {
+			*self.timeout_ms.borrow_mut() = timeout_ms;
 			Ok(())
 		} else {
 			Err(system_to_io_err("SetCommTimeouts", err))
@@ -169,20 +501,52 @@ impl Connection {
 		self.comm_state().and_then(|dcb| self.set_comm_state(DCB{ ByteSize: byte_size, ..dcb }))
 	}
 
-	pub fn parity(&self) -> io::Result<u8> {
-		self.comm_state().map(|dcb| dcb.Parity)
+	pub fn parity(&self) -> io::Result<Parity> {
+		self.comm_state().map(|dcb| Parity::from_byte(dcb.Parity).unwrap_or(Parity::NO))
+	}
+
+	pub fn set_parity(&mut self, parity: Parity) -> io::Result<()> {
+		self.comm_state().and_then(|dcb| self.set_comm_state(DCB{ Parity: parity.to_byte(), ..dcb }))
 	}
 
-	pub fn set_parity(&mut self, parity: u8) -> io::Result<()> {
-		self.comm_state().and_then(|dcb| self.set_comm_state(DCB{ Parity: parity, ..dcb }))
+	pub fn stop_bits(&self) -> io::Result<StopBits> {
+		self.comm_state().map(|dcb| StopBits::from_byte(dcb.StopBits).unwrap_or(StopBits::ONE))
 	}
 
-	pub fn stop_bits(&self) -> io::Result<u8> {
-		self.comm_state().map(|dcb| dcb.StopBits)
+	pub fn set_stop_bits(&mut self, stop_bits: StopBits) -> io::Result<()> {
+		self.comm_state()
+			.and_then(|dcb| self.set_comm_state(DCB{ StopBits: stop_bits.to_byte(), ..dcb }))
 	}
 
-	pub fn set_stop_bits(&mut self, stop_bits: u8) -> io::Result<()> {
-		self.comm_state().and_then(|dcb| self.set_comm_state(DCB{ StopBits: stop_bits, ..dcb }))
+	pub fn flow_control(&self) -> io::Result<FlowControl> {
+		self.comm_state().map(|dcb| flow_control_of_dcb(&dcb))
+	}
+
+	pub fn set_flow_control(&mut self, flow_control: FlowControl) -> io::Result<()> {
+		self.comm_state().and_then(|mut dcb| {
+			apply_flow_control(&mut dcb, &flow_control);
+			self.set_comm_state(dcb)
+		})
+	}
+
+	/// Read every setting covered by `Settings` from the device-control block in one round trip
+	pub fn settings(&self) -> io::Result<Settings> {
+		self.comm_state().map(|dcb| Settings{
+			baud_rate: dcb.BaudRate,
+			byte_size: dcb.ByteSize,
+			parity: Parity::from_byte(dcb.Parity).unwrap_or(Parity::NO),
+			stop_bits: StopBits::from_byte(dcb.StopBits).unwrap_or(StopBits::ONE),
+			flow_control: flow_control_of_dcb(&dcb),
+		})
+	}
+
+	/// Apply every setting in `settings` with a single `GetCommState`+`SetCommState` round trip,
+	/// rather than one round trip per attribute
+	pub fn apply_settings(&mut self, settings: &Settings) -> io::Result<()> {
+		self.comm_state().and_then(|mut dcb| {
+			write_settings_to_dcb(&mut dcb, settings);
+			self.set_comm_state(dcb)
+		})
 	}
 
 	/// Read into `buf` until `delim` is encountered. Return n.o. bytes read on success,
@@ -218,12 +582,99 @@ impl Connection {
 		self.read_until('\n' as u8, buf)
 	}
 }
+
+/// List the names of the available serial ports, e.g. `["COM1", "COM8"]`, sorted numerically
+pub fn available_ports() -> io::Result<Vec<String>> {
+	let mut buf_len = 4096_usize;
+
+	loop {
+		let mut buf: Vec<u16> = (0..buf_len).map(|_| 0).collect();
+		let (n_chars, err) = unsafe { (
+			QueryDosDeviceW(ptr::null(), buf.as_mut_ptr(), buf_len as DWORD),
+			kernel32::GetLastError() as c_int
+		)};
+
+		if n_chars == 0 {
+			if err == ERROR_INSUFFICIENT_BUFFER {
+				buf_len *= 2;
+				continue;
+			}
+			return Err(system_to_io_err("QueryDosDeviceW", err));
+		}
+
+		let mut ports: Vec<String> = buf[..(n_chars as usize - 1)]
+			.split(|&c| c == 0)
+			.filter(|name| !name.is_empty())
+			.map(|name| String::from_utf16_lossy(name))
+			.filter(|name| is_com_port_name(name))
+			.collect();
+
+		ports.sort_by_key(|name| com_port_number(name));
+		return Ok(ports);
+	}
+}
+
+fn is_com_port_name(name: &str) -> bool {
+	name.len() > 3
+		&& name.starts_with("COM")
+		&& name[3..].chars().all(|c| c.is_digit(10))
+}
+
+/// The number after `COM` in a port name, used as the sort key of `available_ports`
+fn com_port_number(name: &str) -> u32 {
+	name[3..].parse::<u32>().unwrap_or(0)
+}
+
+#[test]
+fn is_com_port_name_filters_non_com_devices() {
+	assert!(is_com_port_name("COM1"));
+	assert!(is_com_port_name("COM10"));
+	assert!(!is_com_port_name("LPT1"));
+	assert!(!is_com_port_name("HardDisk0"));
+	assert!(!is_com_port_name("COM"));
+}
+
+#[test]
+fn available_ports_sorts_numerically() {
+	let mut names: Vec<String> = ["COM3", "COM10", "LPT1", "HardDisk0"]
+		.iter()
+		.map(|name| name.to_string())
+		.filter(|name| is_com_port_name(name))
+		.collect();
+
+	names.sort_by_key(|name| com_port_number(name));
+
+	assert_eq!(names, vec!["COM3".to_string(), "COM10".to_string()]);
+}
+
 impl io::Read for Connection {
 	fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
 		if buf.len() == 0 {
 			return Ok(0)
 		}
 
+		if let Some(ref overlapped_io) = *self.overlapped.borrow() {
+			let mut overlapped = unsafe { mem::zeroed::<OVERLAPPED>() };
+			overlapped.hEvent = overlapped_io.read_event;
+
+			// The event is reused across transfers, so it must be non-signaled before each one
+			// or a stale signal from a prior transfer would make the next wait return immediately
+			unsafe { ResetEvent(overlapped_io.read_event); }
+
+			let mut n_bytes_read = 0;
+			let (succeded, err) = unsafe { (
+				kernel32::ReadFile(*self.comm_handle.borrow_mut(),
+					buf.as_mut_ptr() as *mut c_void,
+					buf.len() as u32,
+					&mut n_bytes_read,
+					&mut overlapped) != 0,
+				kernel32::GetLastError() as c_int
+			)};
+
+			return self.finish_overlapped(succeded, err, overlapped_io.read_event, &mut overlapped,
+				*self.timeout_ms.borrow(), n_bytes_read);
+		}
+
 		let mut n_bytes_read = 0;
 		let (succeded, err) = unsafe { (
 			kernel32::ReadFile(*self.comm_handle.borrow_mut(),
@@ -247,6 +698,27 @@ impl io::Read for Connection {
 }
 impl io::Write for Connection {
 	fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+		if let Some(ref overlapped_io) = *self.overlapped.borrow() {
+			let mut overlapped = unsafe { mem::zeroed::<OVERLAPPED>() };
+			overlapped.hEvent = overlapped_io.write_event;
+
+			// See the equivalent reset in `read` above
+			unsafe { ResetEvent(overlapped_io.write_event); }
+
+			let mut n_bytes_written = 0;
+			let (succeded, err) = unsafe { (
+				kernel32::WriteFile(*self.comm_handle.borrow_mut(),
+					mem::transmute(buf.as_ptr()),
+					buf.len() as u32,
+					&mut n_bytes_written,
+					&mut overlapped) != 0,
+				kernel32::GetLastError() as c_int
+			)};
+
+			return self.finish_overlapped(succeded, err, overlapped_io.write_event, &mut overlapped,
+				*self.timeout_ms.borrow(), n_bytes_written);
+		}
+
 		let mut n_bytes_written = 0;
 
 		let (succeded, err) = unsafe { (
@@ -280,7 +752,14 @@ impl io::Write for Connection {
 }
 impl Drop for Connection {
 	fn drop(&mut self) {
-		let e = unsafe { kernel32::CloseHandle(*self.comm_handle.borrow_mut()) };
+		let handle = *self.comm_handle.borrow_mut();
+		// `set_overlapped` leaves the handle at this sentinel if reopening the port failed
+		// partway through; there is then nothing open left to close.
+		if handle == INVALID_HANDLE_VALUE {
+			return;
+		}
+
+		let e = unsafe { kernel32::CloseHandle(handle) };
 		if e == 0 {
 			panic!("Drop of Connection failed. CloseHandle gave error 0x{:x}", e)
 		}